@@ -0,0 +1,233 @@
+//! Extracts a single named Rust item (fn, struct, impl, mod, ...) out of a
+//! source file, instead of vendoring the whole file. The item is located by
+//! parsing the source with `syn`, then the original text is sliced by the
+//! item's span so formatting and comments are preserved verbatim.
+
+use std::error::Error;
+use syn::spanned::Spanned;
+use syn::{File, ImplItem, Item};
+
+/// One item found while walking a parsed file: its module path (for
+/// disambiguation), kind, identifier, and the span to slice out of the
+/// original source.
+struct Candidate {
+    module_path: Vec<String>,
+    kind: &'static str,
+    ident: String,
+    span: proc_macro2::Span,
+}
+
+/// Extracts the item named `name` (optionally qualified by `item_path`, e.g.
+/// `a::b::c`, and optionally restricted to `item_kind`) from `source`.
+///
+/// Returns the original source lines covering the item, preserving formatting
+/// and comments. If the name is ambiguous, returns an error listing the
+/// matching paths so the caller can qualify it with `item_path`. If the source
+/// can't be parsed as a `syn::File` (e.g. it only exists after macro
+/// expansion), falls back to returning the whole file with a warning.
+pub fn extract_item(
+    source: &str,
+    name: &str,
+    item_kind: Option<&str>,
+    item_path: Option<&str>,
+) -> Result<String, Box<dyn Error>> {
+    let parsed: File = match syn::parse_file(source) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            println!("⚠️  Could not parse source as Rust, falling back to whole file: {err}");
+            return Ok(source.to_string());
+        }
+    };
+
+    let mut candidates = Vec::new();
+    collect_candidates(&parsed.items, Vec::new(), &mut candidates);
+
+    let qualifier: Vec<&str> = item_path
+        .map(|p| p.split("::").collect())
+        .unwrap_or_default();
+
+    let mut matches: Vec<&Candidate> = candidates
+        .iter()
+        .filter(|c| c.ident == name)
+        .filter(|c| item_kind.is_none_or(|kind| c.kind == kind))
+        .filter(|c| qualifier.is_empty() || c.module_path == qualifier)
+        .collect();
+
+    match matches.len() {
+        0 => Err(format!(
+            "no item named `{name}`{} found in source (macro-generated items can't be extracted)",
+            item_kind
+                .map(|k| format!(" of kind `{k}`"))
+                .unwrap_or_default()
+        )
+        .into()),
+        1 => Ok(slice_span(source, matches.remove(0).span)),
+        _ => {
+            let paths: Vec<String> = matches.iter().map(|c| c.module_path.join("::")).collect();
+            Err(format!(
+                "`{name}` is ambiguous across modules ({}); disambiguate with --item-path",
+                paths.join(", ")
+            )
+            .into())
+        }
+    }
+}
+
+fn collect_candidates(items: &[Item], module_path: Vec<String>, out: &mut Vec<Candidate>) {
+    for item in items {
+        match item {
+            Item::Mod(item_mod) => {
+                if let Some(ident) = item_ident(item) {
+                    out.push(Candidate {
+                        module_path: module_path.clone(),
+                        kind: item_kind_name(item),
+                        ident,
+                        span: item.span(),
+                    });
+                }
+                if let Some((_, inner_items)) = &item_mod.content {
+                    let mut nested_path = module_path.clone();
+                    nested_path.push(item_mod.ident.to_string());
+                    collect_candidates(inner_items, nested_path, out);
+                }
+            }
+            Item::Impl(item_impl) => {
+                if let Some(self_type) = item_ident(item) {
+                    // A type can have several impl blocks (one inherent, one per
+                    // trait implemented). Without a qualifier they'd all collect
+                    // as indistinguishable "impl Foo" candidates with the same
+                    // module path, making `--item-path` useless for picking one,
+                    // so fold the trait name (or "inherent") into the path.
+                    let mut impl_path = module_path.clone();
+                    impl_path.push(impl_qualifier(item_impl));
+
+                    out.push(Candidate {
+                        module_path: impl_path,
+                        kind: "impl",
+                        ident: self_type.clone(),
+                        span: item.span(),
+                    });
+
+                    for impl_item in &item_impl.items {
+                        if let ImplItem::Fn(method) = impl_item {
+                            let mut method_path = module_path.clone();
+                            method_path.push(self_type.clone());
+                            out.push(Candidate {
+                                module_path: method_path,
+                                kind: "fn",
+                                ident: method.sig.ident.to_string(),
+                                span: method.span(),
+                            });
+                        }
+                    }
+                }
+            }
+            _ => {
+                if let Some(ident) = item_ident(item) {
+                    out.push(Candidate {
+                        module_path: module_path.clone(),
+                        kind: item_kind_name(item),
+                        ident,
+                        span: item.span(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// The path segment used to disambiguate an impl block from others on the
+/// same type: the trait's name for a trait impl, or `"inherent"` otherwise.
+fn impl_qualifier(item_impl: &syn::ItemImpl) -> String {
+    match &item_impl.trait_ {
+        Some((_, path, _)) => path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string())
+            .unwrap_or_else(|| "trait".to_string()),
+        None => "inherent".to_string(),
+    }
+}
+
+fn item_ident(item: &Item) -> Option<String> {
+    match item {
+        Item::Fn(i) => Some(i.sig.ident.to_string()),
+        Item::Struct(i) => Some(i.ident.to_string()),
+        Item::Enum(i) => Some(i.ident.to_string()),
+        Item::Trait(i) => Some(i.ident.to_string()),
+        Item::Mod(i) => Some(i.ident.to_string()),
+        Item::Const(i) => Some(i.ident.to_string()),
+        Item::Static(i) => Some(i.ident.to_string()),
+        Item::Type(i) => Some(i.ident.to_string()),
+        Item::Impl(i) => match &*i.self_ty {
+            syn::Type::Path(type_path) => {
+                type_path.path.segments.last().map(|s| s.ident.to_string())
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn item_kind_name(item: &Item) -> &'static str {
+    match item {
+        Item::Fn(_) => "fn",
+        Item::Struct(_) => "struct",
+        Item::Enum(_) => "enum",
+        Item::Trait(_) => "trait",
+        Item::Mod(_) => "mod",
+        Item::Const(_) => "const",
+        Item::Static(_) => "static",
+        Item::Type(_) => "type",
+        Item::Impl(_) => "impl",
+        _ => "other",
+    }
+}
+
+/// Slices `source` down to the lines covered by `span`, using
+/// `span-locations` line information rather than byte offsets (`syn` spans
+/// don't carry byte ranges outside of a proc-macro context).
+fn slice_span(source: &str, span: proc_macro2::Span) -> String {
+    let start_line = span.start().line;
+    let end_line = span.end().line;
+    source
+        .lines()
+        .skip(start_line.saturating_sub(1))
+        .take(end_line + 1 - start_line)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Without the `span-locations` feature on `proc-macro2`, every span's
+    // start/end line is 0, so `slice_span` silently collapses to just the
+    // first line instead of erroring. A multi-line item is the simplest way
+    // to catch that regression: it fails loudly instead of corrupting output.
+    #[test]
+    fn extracts_a_multi_line_item() {
+        let source = "fn before() {}\n\nfn target() {\n    let x = 1;\n    x + 1\n}\n\nfn after() {}\n";
+        let extracted = extract_item(source, "target", None, None).unwrap();
+        assert_eq!(extracted, "fn target() {\n    let x = 1;\n    x + 1\n}");
+    }
+
+    // Two impl blocks for the same type used to collect as two identical,
+    // unqualified "impl Foo" candidates: the ambiguity error showed nothing
+    // to tell them apart, and --item-path had no way to select either one.
+    #[test]
+    fn disambiguates_multiple_impl_blocks_by_trait() {
+        let source = "struct Foo;\n\nimpl Foo {\n    fn inherent_method() {}\n}\n\nimpl std::fmt::Display for Foo {\n    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {\n        Ok(())\n    }\n}\n";
+
+        let ambiguous = extract_item(source, "Foo", Some("impl"), None).unwrap_err();
+        assert!(ambiguous.to_string().contains("inherent"));
+        assert!(ambiguous.to_string().contains("Display"));
+
+        let inherent = extract_item(source, "Foo", Some("impl"), Some("inherent")).unwrap();
+        assert!(inherent.contains("inherent_method"));
+
+        let display = extract_item(source, "Foo", Some("impl"), Some("Display")).unwrap();
+        assert!(display.contains("fn fmt"));
+    }
+}
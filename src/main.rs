@@ -1,5 +1,9 @@
-use clap::Parser;
-use git2::{FetchOptions, RemoteCallbacks, Repository};
+mod item_extract;
+mod manifest;
+
+use clap::{Parser, Subcommand};
+use git2::{Cred, CredentialType, FetchOptions, RemoteCallbacks, Repository};
+use manifest::{Lockfile, Manifest, ManifestEntry};
 use sha2::{Digest, Sha256};
 use std::error::Error;
 use std::fs;
@@ -7,7 +11,41 @@ use std::path::{Path, PathBuf};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
-struct Args {
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Resolve and save the snippet(s) for each --path, and remember them in the manifest
+    Add(AddArgs),
+    /// Resolve and save every snippet listed in the manifest
+    Sync(SyncArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct NetworkArgs {
+    /// Path to a private SSH key to use when the SSH agent doesn't have a usable identity
+    #[arg(long, env = "GIT_SSH_KEY")]
+    ssh_key: Option<PathBuf>,
+
+    /// Passphrase for --ssh-key, if the key is encrypted
+    #[arg(long)]
+    ssh_key_pass: Option<String>,
+
+    /// Token used for HTTPS authentication (sent as both the username and the password)
+    #[arg(long, env = "GIT_TOKEN")]
+    token: Option<String>,
+
+    /// Directory holding the persistent bare-repo cache (default: `git-hash` under the
+    /// platform cache directory, e.g. ~/.cache/git-hash on Linux)
+    #[arg(long, env = "GIT_HASH_CACHE")]
+    cache_dir: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+struct AddArgs {
     #[arg(long)]
     git: String,
 
@@ -20,8 +58,245 @@ struct Args {
     #[arg(long)]
     commit_hash: Option<String>,
 
+    /// Arbitrary revspec (e.g. `main~3`, `v1.2.3^{commit}`, a short SHA), resolved
+    /// with `git2::Repository::revparse_single` instead of the branch/tag/commit_hash trio
+    #[arg(long)]
+    rev: Option<String>,
+
+    /// File to vendor, or a glob pattern (e.g. `src/**/*.rs`) matched against the
+    /// commit's tree; repeatable to vendor several files/patterns in one run
+    #[arg(long, required = true)]
+    path: Vec<String>,
+
+    #[command(flatten)]
+    network: NetworkArgs,
+
+    /// Refuse to write the snippet unless the resolved commit (or tag object, with
+    /// --tag) carries a signature that verifies against --keyring
+    #[arg(long)]
+    verify_signature: bool,
+
+    /// GPG keyring, or an SSH allowed-signers file, to validate --verify-signature against
+    #[arg(long, required_if_eq("verify_signature", "true"))]
+    keyring: Option<PathBuf>,
+
+    /// Save only the named item (fn, struct, impl, ...) instead of the whole file at --path
     #[arg(long)]
+    item: Option<String>,
+
+    /// Restrict --item to a specific kind when the name alone is ambiguous (fn, struct, impl, mod, ...)
+    #[arg(long)]
+    item_kind: Option<String>,
+
+    /// Qualify --item with its module/self-type path (e.g. `a::b::c`) when the name is ambiguous
+    #[arg(long)]
+    item_path: Option<String>,
+
+    /// Report drift against the existing snippet and exit non-zero instead of writing it
+    #[arg(long, visible_alias = "check")]
+    diff_only: bool,
+
+    /// Manifest file to append this snippet to
+    #[arg(long, default_value = "snippets.toml")]
+    manifest: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+struct SyncArgs {
+    /// Manifest file listing the snippets to resolve and save
+    #[arg(long, default_value = "snippets.toml")]
+    manifest: PathBuf,
+
+    /// Lockfile recording each entry's resolved commit SHA and snippet filename
+    #[arg(long, default_value = "snippets.lock")]
+    lock: PathBuf,
+
+    /// Reuse the pinned commit SHAs from the lockfile instead of re-resolving them; a
+    /// pinned commit that isn't already in the cache is still fetched from the remote
+    #[arg(long, conflicts_with = "update")]
+    locked: bool,
+
+    /// Re-resolve every branch/tag entry to its latest commit and rewrite the lockfile
+    #[arg(long)]
+    update: bool,
+
+    #[command(flatten)]
+    network: NetworkArgs,
+
+    /// Refuse to write a snippet unless its resolved commit (or tag object) carries
+    /// a signature that verifies against --keyring
+    #[arg(long)]
+    verify_signature: bool,
+
+    /// GPG keyring, or an SSH allowed-signers file, to validate --verify-signature against
+    #[arg(long, required_if_eq("verify_signature", "true"))]
+    keyring: Option<PathBuf>,
+
+    /// Report drift for every entry and exit non-zero instead of writing anything (CI mode)
+    #[arg(long, visible_alias = "check")]
+    diff_only: bool,
+}
+
+/// A single snippet to resolve, regardless of whether it came from `add`'s
+/// CLI flags or a `snippets.toml` entry loaded by `sync`.
+struct SnippetRequest {
+    git: String,
+    branch: Option<String>,
+    tag: Option<String>,
+    commit_hash: Option<String>,
+    rev: Option<String>,
     path: String,
+    item: Option<String>,
+    item_kind: Option<String>,
+    item_path: Option<String>,
+    verify_signature: bool,
+    keyring: Option<PathBuf>,
+    diff_only: bool,
+}
+
+impl SnippetRequest {
+    fn from_add_args(args: &AddArgs, path: String) -> Self {
+        Self {
+            git: args.git.clone(),
+            branch: args.branch.clone(),
+            tag: args.tag.clone(),
+            commit_hash: args.commit_hash.clone(),
+            rev: args.rev.clone(),
+            path,
+            item: args.item.clone(),
+            item_kind: args.item_kind.clone(),
+            item_path: args.item_path.clone(),
+            verify_signature: args.verify_signature,
+            keyring: args.keyring.clone(),
+            diff_only: args.diff_only,
+        }
+    }
+
+    fn from_manifest_entry(
+        entry: &ManifestEntry,
+        verify_signature: bool,
+        keyring: Option<PathBuf>,
+        diff_only: bool,
+    ) -> Self {
+        Self {
+            git: entry.git.clone(),
+            branch: entry.branch.clone(),
+            tag: entry.tag.clone(),
+            commit_hash: entry.commit.clone(),
+            rev: entry.rev_spec.clone(),
+            path: entry.path.clone(),
+            item: entry.item.clone(),
+            item_kind: entry.item_kind.clone(),
+            item_path: entry.item_path.clone(),
+            verify_signature,
+            keyring,
+            diff_only,
+        }
+    }
+
+    fn as_manifest_entry(&self) -> ManifestEntry {
+        ManifestEntry {
+            git: self.git.clone(),
+            branch: self.branch.clone(),
+            tag: self.tag.clone(),
+            commit: self.commit_hash.clone(),
+            rev_spec: self.rev.clone(),
+            path: self.path.clone(),
+            item: self.item.clone(),
+            item_kind: self.item_kind.clone(),
+            item_path: self.item_path.clone(),
+        }
+    }
+}
+
+/// Credentials available for authenticating against a remote, threaded through
+/// every `git2` operation that might need them.
+struct AuthConfig {
+    ssh_key: Option<PathBuf>,
+    ssh_key_pass: Option<String>,
+    token: Option<String>,
+}
+
+impl AuthConfig {
+    fn from_network_args(network: &NetworkArgs) -> Self {
+        Self {
+            ssh_key: network.ssh_key.clone(),
+            ssh_key_pass: network.ssh_key_pass.clone(),
+            token: network.token.clone(),
+        }
+    }
+
+    /// Builds a `RemoteCallbacks` whose `credentials` handler tries, in order: the
+    /// SSH agent, an explicit SSH key pair, then a username/token for HTTPS.
+    ///
+    /// A fresh set of "already tried" cells is created per call (i.e. per
+    /// connect/fetch), not stored on `AuthConfig` itself: libgit2 re-invokes the
+    /// callback as long as each attempt is rejected, including when
+    /// `Cred::ssh_key_from_agent` constructs fine but the agent's key isn't one
+    /// the server accepts, so within one negotiation a rejected attempt needs to
+    /// fall through to the next method instead of being retried forever. But a
+    /// run does several separate network operations (e.g. resolving the default
+    /// branch, then fetching it), each with its own handshake, so the state must
+    /// not survive past the operation it was created for either.
+    fn callbacks(&self) -> RemoteCallbacks<'_> {
+        let agent_tried = std::cell::Cell::new(false);
+        let ssh_key_tried = std::cell::Cell::new(false);
+        let token_tried = std::cell::Cell::new(false);
+
+        let mut callbacks = RemoteCallbacks::new();
+        callbacks.credentials(move |url, username_from_url, allowed_types| {
+            self.credentials(
+                url,
+                username_from_url,
+                allowed_types,
+                &agent_tried,
+                &ssh_key_tried,
+                &token_tried,
+            )
+        });
+        callbacks
+    }
+
+    fn credentials(
+        &self,
+        url: &str,
+        username_from_url: Option<&str>,
+        allowed_types: CredentialType,
+        agent_tried: &std::cell::Cell<bool>,
+        ssh_key_tried: &std::cell::Cell<bool>,
+        token_tried: &std::cell::Cell<bool>,
+    ) -> Result<Cred, git2::Error> {
+        let username = username_from_url.unwrap_or("git");
+
+        // Some SSH URLs carry no username, in which case libgit2 asks for one
+        // (via CredentialType::USERNAME) before it will even offer SSH_KEY.
+        if allowed_types.contains(CredentialType::USERNAME) {
+            return Cred::username(username);
+        }
+
+        if allowed_types.contains(CredentialType::SSH_KEY) {
+            if !agent_tried.replace(true) {
+                if let Ok(cred) = Cred::ssh_key_from_agent(username) {
+                    return Ok(cred);
+                }
+            }
+            if !ssh_key_tried.replace(true) {
+                if let Some(ssh_key) = &self.ssh_key {
+                    return Cred::ssh_key(username, None, ssh_key, self.ssh_key_pass.as_deref());
+                }
+            }
+        }
+
+        if allowed_types.contains(CredentialType::USER_PASS_PLAINTEXT) && !token_tried.replace(true) {
+            if let Some(token) = &self.token {
+                return Cred::userpass_plaintext(token, token);
+            }
+        }
+
+        Err(git2::Error::from_str(&format!(
+            "no usable credentials for {url} (tried SSH agent, --ssh-key, --token)"
+        )))
+    }
 }
 
 // Helper functions for consistent hashing
@@ -41,7 +316,304 @@ fn hash_git_option(option_type: &str, value: &str) -> String {
     hash_string(&format!("{}-{}", option_type, value))
 }
 
+/// Resolves the root of the persistent bare-repo cache: `--cache-dir`, then
+/// `GIT_HASH_CACHE` (handled by clap's `env`), then `git-hash` under the
+/// platform cache directory (e.g. `~/.cache/git-hash` on Linux).
+fn resolve_cache_dir(network: &NetworkArgs) -> Result<PathBuf, Box<dyn Error>> {
+    if let Some(dir) = &network.cache_dir {
+        return Ok(dir.clone());
+    }
+
+    let base = dirs::cache_dir().ok_or("could not determine a cache directory for this platform")?;
+    Ok(base.join("git-hash"))
+}
+
+/// Opens the bare repo that caches a given remote URL, initializing it first
+/// if this is the first time the URL has been seen.
+fn open_or_init_cache_repo(cache_dir: &Path, git_url: &str) -> Result<Repository, Box<dyn Error>> {
+    fs::create_dir_all(cache_dir)?;
+    let repo_path = cache_dir.join(hash_git_url(git_url));
+
+    if repo_path.exists() {
+        Ok(Repository::open_bare(&repo_path)?)
+    } else {
+        println!("ℹ️  No cache entry for this URL yet, initializing bare repo...");
+        Ok(Repository::init_bare(&repo_path)?)
+    }
+}
+
+/// Opens (or initializes) the bare repo that caches a given remote URL, fetching
+/// the requested commit into it only if it isn't already present locally. This
+/// mirrors how Nix's git fetcher keeps one cached bare repo per remote under
+/// `gitv3`, keyed by revision.
+fn ensure_commit_cached(
+    cache_dir: &Path,
+    git_url: &str,
+    commit_sha: &str,
+    auth: &AuthConfig,
+) -> Result<Repository, Box<dyn Error>> {
+    let repo = open_or_init_cache_repo(cache_dir, git_url)?;
+
+    let commit_id = git2::Oid::from_str(commit_sha)?;
+    if repo.find_commit(commit_id).is_ok() {
+        println!("✅ Commit {} already present in cache", commit_sha);
+        return Ok(repo);
+    }
+
+    println!("📥 Commit not cached yet, fetching {} from remote...", commit_sha);
+    {
+        let mut remote = repo.remote_anonymous(git_url)?;
+        let mut fetch_opts = FetchOptions::new();
+        fetch_opts.remote_callbacks(auth.callbacks());
+        remote.fetch(&[commit_sha], Some(&mut fetch_opts), None)?;
+    }
+
+    Ok(repo)
+}
+
+/// Resolves an arbitrary `--rev` revspec (e.g. `main~3`, `v1.2.3^{commit}`, a
+/// short SHA) against a remote, returning the full commit OID it points to.
+///
+/// Unlike `ensure_commit_cached`, which fetches a single known commit, a
+/// revspec like `main~3` needs ancestry to walk, so this always fetches every
+/// branch and tag in full (no single-commit shortcut) before asking
+/// `git2::Repository::revparse_single` to resolve it locally.
+fn resolve_revspec_commit(
+    cache_dir: &Path,
+    git_url: &str,
+    revspec: &str,
+    auth: &AuthConfig,
+) -> Result<String, Box<dyn Error>> {
+    let repo = open_or_init_cache_repo(cache_dir, git_url)?;
+
+    println!("📥 Fetching full ref history to resolve `--rev {revspec}`...");
+    let mut remote = repo.remote_anonymous(git_url)?;
+    let mut fetch_opts = FetchOptions::new();
+    fetch_opts.remote_callbacks(auth.callbacks());
+    fetch_opts.download_tags(git2::AutotagOption::All);
+    // Mirror branches straight into refs/heads/* (rather than refs/remotes/origin/*)
+    // so a bare `main` in --rev resolves directly, matching a normal bare clone.
+    remote.fetch(
+        &["+refs/heads/*:refs/heads/*", "+refs/tags/*:refs/tags/*"],
+        Some(&mut fetch_opts),
+        None,
+    )?;
+
+    println!("🔍 Resolving revspec `{revspec}`...");
+    let commit = repo.revparse_single(revspec)?.peel_to_commit()?;
+    let commit_sha = commit.id().to_string();
+    println!("✅ `{revspec}` resolved to commit {commit_sha}");
+
+    Ok(commit_sha)
+}
+
+/// Resolves a `--path` value, which may be a literal path or a glob pattern
+/// (e.g. `src/**/*.rs`), against the commit's tree, returning every matching
+/// blob path. Matching is done by walking the tree with `Tree::walk` rather
+/// than the working directory, since nothing is checked out to disk.
+fn resolve_tree_paths(
+    repo: &Repository,
+    commit_sha: &str,
+    pattern: &str,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    if !pattern.contains(['*', '?', '[', '{']) {
+        return Ok(vec![pattern.to_string()]);
+    }
+
+    let mut builder = globset::GlobSetBuilder::new();
+    builder.add(globset::Glob::new(pattern)?);
+    let glob_set = builder.build()?;
+
+    let commit_id = git2::Oid::from_str(commit_sha)?;
+    let commit = repo.find_commit(commit_id)?;
+    let tree = commit.tree()?;
+
+    let mut matches = Vec::new();
+    tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+        if entry.kind() == Some(git2::ObjectType::Blob) {
+            if let Some(name) = entry.name() {
+                let full_path = format!("{root}{name}");
+                if glob_set.is_match(&full_path) {
+                    matches.push(full_path);
+                }
+            }
+        }
+        git2::TreeWalkResult::Ok
+    })?;
+
+    if matches.is_empty() {
+        return Err(format!("no files in the tree matched `{pattern}`").into());
+    }
+
+    Ok(matches)
+}
+
+/// Reads a single file out of a cached commit's tree without checking anything
+/// out to disk.
+fn read_file_at_commit(
+    repo: &Repository,
+    commit_sha: &str,
+    path: &str,
+) -> Result<String, Box<dyn Error>> {
+    let commit_id = git2::Oid::from_str(commit_sha)?;
+    let commit = repo.find_commit(commit_id)?;
+    let tree = commit.tree()?;
+    let blob = tree
+        .get_path(Path::new(path))?
+        .to_object(repo)?
+        .peel_to_blob()?;
+    Ok(String::from_utf8(blob.content().to_vec())?)
+}
+
+/// Renders a unified diff between the currently-vendored snippet and the
+/// freshly-resolved one, so reviewers can see exactly what changed upstream
+/// before it overwrites the file on disk.
+fn render_snippet_diff(
+    repo: &Repository,
+    label: &str,
+    old_content: &str,
+    new_content: &str,
+) -> Result<String, Box<dyn Error>> {
+    let old_blob = repo.find_blob(repo.blob(old_content.as_bytes())?)?;
+    let new_blob = repo.find_blob(repo.blob(new_content.as_bytes())?)?;
+
+    let rendered = std::cell::RefCell::new(String::new());
+    repo.diff_blobs(
+        Some(&old_blob),
+        Some(label),
+        Some(&new_blob),
+        Some(label),
+        None,
+        None,
+        None,
+        Some(&mut |_delta, hunk| {
+            rendered
+                .borrow_mut()
+                .push_str(&String::from_utf8_lossy(hunk.header()));
+            true
+        }),
+        Some(&mut |_delta, _hunk, line| {
+            let mut rendered = rendered.borrow_mut();
+            match line.origin() {
+                '+' | '-' | ' ' => rendered.push(line.origin()),
+                _ => {}
+            }
+            rendered.push_str(&String::from_utf8_lossy(line.content()));
+            true
+        }),
+    )?;
+
+    Ok(rendered.into_inner())
+}
+
+/// Verifies that the git object at `object_id` (a commit, or an annotated tag
+/// object when `--tag` is used) carries a signature that validates against
+/// `keyring`, the way captain-git-hook's `verify_commit_signature`/
+/// `verify_tag_signature` gate commits against a keyring. Aborts the whole
+/// operation on any failure so nothing gets written under `.snippets`.
+fn verify_object_signature(
+    repo: &Repository,
+    object_id: git2::Oid,
+    keyring: &Path,
+) -> Result<(), Box<dyn Error>> {
+    // `extract_signature` only reads commits; a signed annotated tag carries its
+    // signature at the end of its raw object data instead.
+    let (signature, signed_data) = if repo.find_tag(object_id).is_ok() {
+        let raw = String::from_utf8(repo.odb()?.read(object_id)?.data().to_vec())?;
+        let start = raw
+            .rfind("-----BEGIN ")
+            .ok_or_else(|| format!("object {object_id} has no signature to verify"))?;
+        (raw[start..].to_string(), raw[..start].to_string())
+    } else {
+        let (signature, signed_data) = repo
+            .extract_signature(&object_id, None)
+            .map_err(|e| format!("object {object_id} has no signature to verify: {e}"))?;
+        (String::from_utf8(signature.to_vec())?, String::from_utf8(signed_data.to_vec())?)
+    };
+
+    if signature.trim_start().starts_with("-----BEGIN SSH SIGNATURE-----") {
+        let signer_identity = signer_email(repo, object_id)?;
+        verify_ssh_signature(&signature, &signed_data, keyring, &signer_identity)
+    } else {
+        verify_gpg_signature(&signature, &signed_data, keyring)
+    }
+}
+
+/// The committer email (for a commit) or tagger email (for an annotated tag
+/// object), used as the `-I` principal `ssh-keygen -Y verify` looks up in the
+/// allowed-signers file.
+fn signer_email(repo: &Repository, object_id: git2::Oid) -> Result<String, Box<dyn Error>> {
+    if let Ok(commit) = repo.find_commit(object_id) {
+        return commit
+            .committer()
+            .email()
+            .map(str::to_string)
+            .ok_or_else(|| "commit committer has no email".into());
+    }
+    if let Ok(tag) = repo.find_tag(object_id) {
+        return tag
+            .tagger()
+            .and_then(|t| t.email().map(str::to_string))
+            .ok_or_else(|| "tag has no tagger email".into());
+    }
+    Err(format!("object {object_id} is neither a commit nor an annotated tag").into())
+}
+
+fn verify_gpg_signature(signature: &str, payload: &str, keyring: &Path) -> Result<(), Box<dyn Error>> {
+    let mut payload_file = tempfile::NamedTempFile::new()?;
+    std::io::Write::write_all(&mut payload_file, payload.as_bytes())?;
+    let mut sig_file = tempfile::NamedTempFile::new()?;
+    std::io::Write::write_all(&mut sig_file, signature.as_bytes())?;
+
+    let status = std::process::Command::new("gpg")
+        .arg("--no-default-keyring")
+        .arg("--keyring")
+        .arg(keyring)
+        .arg("--verify")
+        .arg(sig_file.path())
+        .arg(payload_file.path())
+        .status()?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err("GPG signature verification failed against the supplied keyring".into())
+    }
+}
+
+fn verify_ssh_signature(
+    signature: &str,
+    payload: &str,
+    allowed_signers: &Path,
+    signer_identity: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mut sig_file = tempfile::NamedTempFile::new()?;
+    std::io::Write::write_all(&mut sig_file, signature.as_bytes())?;
+
+    let mut child = std::process::Command::new("ssh-keygen")
+        .arg("-Y")
+        .arg("verify")
+        .arg("-f")
+        .arg(allowed_signers)
+        .arg("-I")
+        .arg(signer_identity)
+        .arg("-n")
+        .arg("git")
+        .arg("-s")
+        .arg(sig_file.path())
+        .stdin(std::process::Stdio::piped())
+        .spawn()?;
+    std::io::Write::write_all(child.stdin.as_mut().ok_or("failed to open ssh-keygen stdin")?, payload.as_bytes())?;
+
+    if child.wait()?.success() {
+        Ok(())
+    } else {
+        Err("SSH signature verification failed against the supplied allowed-signers file".into())
+    }
+}
+
 /// Represents a parsed snippet filename
+#[derive(Clone)]
 struct SnippetFile {
     prefix: String,
     commit_hash: String,
@@ -56,6 +628,7 @@ impl SnippetFile {
         git_option_value: &str,
         path: &str,
         commit_sha: &str,
+        item: Option<&str>,
     ) -> Self {
         let path_buf = PathBuf::from(path);
         let file_name = path_buf
@@ -63,11 +636,18 @@ impl SnippetFile {
             .and_then(|f| f.to_str())
             .unwrap_or("unknown");
 
+        // Fold the item name into the path hash so multiple items extracted
+        // from the same file don't collide on the same snippet filename.
+        let path_key = match item {
+            Some(item) => format!("{path}#{item}"),
+            None => path.to_string(),
+        };
+
         let prefix = format!(
             "{}-{}-{}-{}",
             hash_git_url(git_url),
             hash_git_option(git_option_type, git_option_value),
-            hash_string(path),
+            hash_string(&path_key),
             file_name,
         );
 
@@ -111,105 +691,324 @@ impl SnippetFile {
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let args = Args::parse();
+    let cli = Cli::parse();
 
-    // Validate that only one of branch, tag, or commit_hash is provided
+    match cli.command {
+        Command::Add(args) => run_add(args),
+        Command::Sync(args) => run_sync(args),
+    }
+}
+
+fn run_add(args: AddArgs) -> Result<(), Box<dyn Error>> {
     let options_count = [
         args.branch.is_some(),
         args.tag.is_some(),
         args.commit_hash.is_some(),
+        args.rev.is_some(),
     ]
     .iter()
     .filter(|&&x| x)
     .count();
 
     if options_count > 1 {
-        return Err("Only one of --branch, --tag, or --commit_hash can be specified".into());
+        return Err("Only one of --branch, --tag, --commit-hash, or --rev can be specified".into());
     }
 
-    println!("\n🔍 Fetching commit SHA from remote repository...");
+    let auth = AuthConfig::from_network_args(&args.network);
+    let cache_dir = resolve_cache_dir(&args.network)?;
+    let manifest_path = args.manifest.clone();
+    let mut manifest = Manifest::load(&manifest_path)?;
 
-    // Determine git option type and value for hashing
-    let (git_option_type, git_option_value, commit_sha) = if let Some(hash) = args.commit_hash {
-        ("commit".to_string(), hash.clone(), hash)
-    } else if let Some(tag) = &args.tag {
-        (
-            "tag".to_string(),
-            tag.clone(),
-            get_remote_commit_sha_without_clone(&args.git, None, Some(tag))?,
+    for path in &args.path {
+        let request = SnippetRequest::from_add_args(&args, path.clone());
+
+        resolve_and_save_snippet(&request, &auth, &cache_dir)?;
+
+        println!("\n📝 Recording snippet in manifest...");
+        manifest.append(request.as_manifest_entry());
+    }
+
+    manifest.save(&manifest_path)?;
+    println!("✅ Manifest saved to {}", manifest_path.display());
+
+    Ok(())
+}
+
+fn run_sync(args: SyncArgs) -> Result<(), Box<dyn Error>> {
+    let auth = AuthConfig::from_network_args(&args.network);
+    let cache_dir = resolve_cache_dir(&args.network)?;
+    let manifest = Manifest::load(&args.manifest)?;
+    let mut lock = Lockfile::load(&args.lock)?;
+
+    if manifest.entries.is_empty() {
+        println!("ℹ️  No entries in {}, nothing to sync", args.manifest.display());
+        return Ok(());
+    }
+
+    // Under --diff-only every entry should be checked and reported, rather
+    // than aborting the whole run at the first one that's drifted.
+    let mut drifted = Vec::new();
+
+    for entry in &manifest.entries {
+        println!("\n=== Syncing {} @ {} -> {} ===", entry.git, entry.rev(), entry.path);
+
+        let request = SnippetRequest::from_manifest_entry(
+            entry,
+            args.verify_signature,
+            args.keyring.clone(),
+            args.diff_only,
+        );
+
+        // Like Cargo/Nix: plain `sync` trusts the lockfile when an entry is
+        // already pinned there, `--locked` requires it to be (CI mode, no
+        // network surprises), and `--update` is the only mode that re-resolves
+        // branch/tag entries to their latest commit.
+        let locked_entry = if args.update { None } else { lock.find(entry) };
+
+        let outcome = if let Some(locked_entry) = locked_entry {
+            println!("🔒 Reusing pinned commit {} from lockfile", locked_entry.commit);
+            resolve_and_save_snippet_at_commit(&request, &locked_entry.commit, &auth, &cache_dir)
+                .map(|outcome| (locked_entry.commit.clone(), outcome))
+        } else if args.locked {
+            Err(
+                "--locked requires every manifest entry to already have a lockfile entry; run `sync` once without --locked first"
+                    .into(),
+            )
+        } else {
+            resolve_and_save_snippet(&request, &auth, &cache_dir)
+                .map(|outcome| (outcome[0].commit_hash.clone(), outcome))
+        };
+
+        let (commit_sha, snippet_files) = match outcome {
+            Ok(pair) => pair,
+            Err(e) if args.diff_only => {
+                drifted.push(format!("{} @ {} -> {}: {e}", entry.git, entry.rev(), entry.path));
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let snippet_names = snippet_files.into_iter().map(|s| s.full_name).collect();
+        lock.upsert(entry, commit_sha, snippet_names);
+    }
+
+    if !drifted.is_empty() {
+        return Err(format!(
+            "drift detected in {} of {} entry(s):\n{}",
+            drifted.len(),
+            manifest.entries.len(),
+            drifted.join("\n")
         )
+        .into());
+    }
+
+    if args.diff_only {
+        println!("\n✅ No drift detected; lockfile left unchanged");
+        return Ok(());
+    }
+
+    lock.save(&args.lock)?;
+    println!("\n✅ Lockfile written to {}", args.lock.display());
+
+    Ok(())
+}
+
+/// Resolves a snippet's commit SHA from its branch/tag/commit/rev selector,
+/// then fetches, verifies, extracts, and saves it.
+fn resolve_and_save_snippet(
+    request: &SnippetRequest,
+    auth: &AuthConfig,
+    cache_dir: &Path,
+) -> Result<Vec<SnippetFile>, Box<dyn Error>> {
+    println!("\n🔍 Fetching commit SHA from remote repository...");
+
+    let commit_sha = if let Some(revspec) = &request.rev {
+        resolve_revspec_commit(cache_dir, &request.git, revspec, auth)?
+    } else if let Some(hash) = &request.commit_hash {
+        hash.clone()
+    } else if let Some(tag) = &request.tag {
+        get_remote_commit_sha_without_clone(cache_dir, &request.git, None, Some(tag), auth)?
+    } else {
+        let branch_name = match &request.branch {
+            Some(branch) => branch.clone(),
+            None => get_default_branch(&request.git, auth)?,
+        };
+        get_remote_commit_sha_without_clone(cache_dir, &request.git, Some(&branch_name), None, auth)?
+    };
+
+    println!("✅ Found commit SHA: {}", commit_sha);
+    resolve_and_save_snippet_at_commit(request, &commit_sha, auth, cache_dir)
+}
+
+/// Like `resolve_and_save_snippet`, but skips resolving the revision and uses
+/// an already-known commit SHA (used by `sync --locked`). `request.path` may
+/// be a glob, in which case one snippet file is produced per matching blob.
+fn resolve_and_save_snippet_at_commit(
+    request: &SnippetRequest,
+    commit_sha: &str,
+    auth: &AuthConfig,
+    cache_dir: &Path,
+) -> Result<Vec<SnippetFile>, Box<dyn Error>> {
+    let (git_option_type, git_option_value) = if let Some(revspec) = &request.rev {
+        ("rev".to_string(), revspec.clone())
+    } else if let Some(hash) = &request.commit_hash {
+        ("commit".to_string(), hash.clone())
+    } else if let Some(tag) = &request.tag {
+        ("tag".to_string(), tag.clone())
     } else {
-        // Handle branch case (including default branch)
-        let default_branch = get_default_branch(&args.git)?;
-        let branch_name = args
-            .branch
-            .as_deref()
-            .unwrap_or(&default_branch)
-            .to_string();
         (
             "branch".to_string(),
-            branch_name.clone(),
-            get_remote_commit_sha_without_clone(&args.git, Some(&branch_name), None)?,
+            request.branch.clone().unwrap_or_else(|| "HEAD".to_string()),
         )
     };
 
-    println!("✅ Found commit SHA: {}", commit_sha);
-    println!("ℹ️  Git URL hash: {}", hash_git_url(&args.git));
+    println!("\n📦 Resolving cached bare repo for this remote...");
+    let repo = ensure_commit_cached(cache_dir, &request.git, commit_sha, auth)?;
+
+    println!("\n🔎 Resolving --path against the commit tree...");
+    let matched_paths = resolve_tree_paths(&repo, commit_sha, &request.path)?;
+    println!("✅ Matched {} file(s): {:?}", matched_paths.len(), matched_paths);
+
+    if request.item.is_some() && matched_paths.len() > 1 {
+        return Err(format!(
+            "--item requires --path to match exactly one file, but `{}` matched {}",
+            request.path,
+            matched_paths.len()
+        )
+        .into());
+    }
+
+    if request.verify_signature {
+        println!("\n🔏 Verifying signature before writing snippet(s)...");
+        let keyring = request
+            .keyring
+            .as_ref()
+            .ok_or("--keyring is required with --verify-signature")?;
+
+        let signing_object = if let Some(tag_name) = &request.tag {
+            let mut remote = repo.remote_anonymous(&request.git)?;
+            let mut fetch_opts = FetchOptions::new();
+            fetch_opts.remote_callbacks(auth.callbacks());
+            remote.fetch(
+                &[format!("refs/tags/{tag_name}:refs/tags/{tag_name}")],
+                Some(&mut fetch_opts),
+                None,
+            )?;
+            repo.find_reference(&format!("refs/tags/{tag_name}"))?
+                .target()
+                .ok_or("tag reference has no direct target")?
+        } else {
+            git2::Oid::from_str(commit_sha)?
+        };
+
+        verify_object_signature(&repo, signing_object, keyring)?;
+        println!("✅ Signature verified");
+    }
+
+    let mut saved = Vec::with_capacity(matched_paths.len());
+    for matched_path in &matched_paths {
+        saved.push(save_one_snippet(
+            request,
+            &repo,
+            commit_sha,
+            &git_option_type,
+            &git_option_value,
+            matched_path,
+        )?);
+    }
+
+    Ok(saved)
+}
+
+/// Resolves, diffs, and writes a single matched file as a snippet.
+fn save_one_snippet(
+    request: &SnippetRequest,
+    repo: &Repository,
+    commit_sha: &str,
+    git_option_type: &str,
+    git_option_value: &str,
+    matched_path: &str,
+) -> Result<SnippetFile, Box<dyn Error>> {
+    println!("ℹ️  Git URL hash: {}", hash_git_url(&request.git));
     println!(
         "ℹ️  Git option hash: {}",
-        hash_git_option(&git_option_type, &git_option_value)
+        hash_git_option(git_option_type, git_option_value)
     );
-    println!("ℹ️  Path hash: {}", hash_string(&args.path));
+    println!("ℹ️  Path hash: {}", hash_string(matched_path));
 
-    // Create new snippet file object
     let new_snippet = SnippetFile::new(
-        &args.git,
-        &git_option_type,
-        &git_option_value,
-        &args.path,
-        &commit_sha,
+        &request.git,
+        git_option_type,
+        git_option_value,
+        matched_path,
+        commit_sha,
+        request.item.as_deref(),
     );
 
     println!("\n🔍 Checking for existing snippets...");
 
-    // Check for existing snippet with same prefix
-    if let Some(existing_snippet) = SnippetFile::find_existing(&new_snippet.prefix) {
+    let existing_snippet = SnippetFile::find_existing(&new_snippet.prefix);
+    if let Some(existing_snippet) = &existing_snippet {
         if existing_snippet.commit_hash == commit_sha {
             println!(
                 "✅ Existing snippet is up to date at: .snippets/{}",
                 existing_snippet.full_name
             );
-            return Ok(());
+            return Ok(existing_snippet.clone());
         } else {
             println!("ℹ️  Found existing snippet with different commit hash:");
             println!("   Current: {}", existing_snippet.commit_hash);
             println!("   New: {}", commit_sha);
-            println!("🔄 Updating snippet...");
+        }
+    } else if request.diff_only {
+        println!("ℹ️  No existing snippet found at: .snippets/{}", new_snippet.full_name);
+    }
 
-            // Remove existing snippet
-            fs::remove_file(Path::new(".snippets").join(&existing_snippet.full_name))?;
+    println!("\n📄 Reading source file from cached commit...");
+    let content = read_file_at_commit(repo, commit_sha, matched_path)?;
+    println!("✅ Successfully read file");
+
+    let content = match &request.item {
+        Some(item) => {
+            println!("\n✂️  Extracting item `{item}`...");
+            let extracted = item_extract::extract_item(
+                &content,
+                item,
+                request.item_kind.as_deref(),
+                request.item_path.as_deref(),
+            )?;
+            println!("✅ Extracted item");
+            extracted
+        }
+        None => content,
+    };
+
+    if let Some(existing_snippet) = &existing_snippet {
+        let old_content = fs::read_to_string(Path::new(".snippets").join(&existing_snippet.full_name))?;
+        if old_content != content {
+            let diff = render_snippet_diff(repo, matched_path, &old_content, &content)?;
+            println!("\n--- Diff against the vendored snippet ---");
+            print!("{diff}");
+            println!("--- End diff ---");
         }
     }
 
-    // Create .snippets directory if it doesn't exist
+    if request.diff_only {
+        return Err(format!(
+            "drift detected for .snippets/{} (use without --diff-only to write it)",
+            new_snippet.full_name
+        )
+        .into());
+    }
+
     println!("\n📁 Creating .snippets directory if it doesn't exist...");
     std::fs::create_dir_all(".snippets")?;
     println!("✅ .snippets directory ready");
 
-    // Clone repo and get content only if we need to update
-    println!("\n📦 Cloning repository and checking out specific commit...");
-    let temp_dir = clone_and_checkout_repo(
-        &args.git,
-        args.branch.as_deref(),
-        args.tag.as_deref(),
-        &commit_sha,
-    )?;
-    println!("✅ Repository cloned successfully");
-
-    println!("\n📄 Reading source file...");
-    let source_path = temp_dir.path().join(&args.path);
-    let content = std::fs::read_to_string(&source_path)?;
-    println!("✅ Successfully read file");
+    if let Some(existing_snippet) = &existing_snippet {
+        println!("🔄 Updating snippet...");
+        fs::remove_file(Path::new(".snippets").join(&existing_snippet.full_name))?;
+    }
 
     println!("\n💾 Saving snippet...");
     let snippet_path = Path::new(".snippets").join(&new_snippet.full_name);
@@ -219,129 +1018,59 @@ fn main() -> Result<(), Box<dyn Error>> {
     println!("\n📊 Summary:");
     println!("- Commit SHA: {}", commit_sha);
     println!("- Snippet saved to: .snippets/{}", new_snippet.full_name);
-
-    // Prevent temp_dir from being deleted
-    std::mem::forget(temp_dir);
     println!("\n✨ Operation completed successfully!");
 
-    Ok(())
+    Ok(new_snippet)
 }
 
+/// Resolves a branch or tag (the caller always supplies exactly one; the
+/// default-branch name, if needed, is resolved by the caller beforehand via
+/// `get_default_branch`) to its current commit SHA by fetching just that ref
+/// into the persistent bare-repo cache (the same one keyed by URL in
+/// `ensure_commit_cached`), rather than a throwaway clone. Because the commit
+/// lands in the cache as a side effect, the `ensure_commit_cached` call that
+/// follows finds it already present instead of fetching it a second time.
 fn get_remote_commit_sha_without_clone(
+    cache_dir: &Path,
     git_url: &str,
     branch: Option<&str>,
     tag: Option<&str>,
+    auth: &AuthConfig,
 ) -> Result<String, Box<dyn Error>> {
-    let temp_dir = tempfile::Builder::new()
-        .prefix("docify-temp-")
-        .rand_bytes(5)
-        .tempdir()?;
-
-    let repo = Repository::init(temp_dir.path())?;
-    let mut remote = repo.remote_anonymous(git_url)?;
-
-    // First, fetch the remote HEAD to determine default branch
-    println!("ℹ️  Fetching remote references...");
-    remote.connect(git2::Direction::Fetch)?;
-    let default_branch = remote
-        .default_branch()?
-        .as_str()
-        .ok_or("Invalid default branch name")?
-        .to_string();
-    remote.disconnect()?;
+    let repo = open_or_init_cache_repo(cache_dir, git_url)?;
 
-    // Convert refs/heads/main to just main
-    let default_branch = default_branch
-        .strip_prefix("refs/heads/")
-        .unwrap_or(&default_branch);
-
-    println!("ℹ️  Default branch: {}", default_branch);
-
-    // Determine which refs to fetch
-    let refspecs = if let Some(tag_name) = tag {
-        vec![format!("refs/tags/{}:refs/tags/{}", tag_name, tag_name)]
+    // Determine which ref to fetch
+    let refspec = if let Some(tag_name) = tag {
+        format!("refs/tags/{tag_name}:refs/tags/{tag_name}")
     } else {
-        let branch_name = branch.unwrap_or(default_branch);
-        vec![format!(
-            "refs/heads/{}:refs/heads/{}",
-            branch_name, branch_name
-        )]
+        let branch_name = branch.ok_or("either a branch or a tag must be specified")?;
+        format!("refs/heads/{branch_name}:refs/heads/{branch_name}")
     };
 
-    println!("ℹ️  Refspecs: {:?}", refspecs);
+    println!("ℹ️  Refspec: {refspec}");
 
-    // Fetch the required refs
-    println!("ℹ️  Fetching required references...");
-    remote.fetch(
-        refspecs
-            .iter()
-            .map(|s| s.as_str())
-            .collect::<Vec<_>>()
-            .as_slice(),
-        None,
-        None,
-    )?;
+    // Fetch the required ref into the cache
+    println!("ℹ️  Fetching required reference...");
+    let commit_id = {
+        let mut remote = repo.remote_anonymous(git_url)?;
+        let mut fetch_opts = FetchOptions::new();
+        fetch_opts.remote_callbacks(auth.callbacks());
+        remote.fetch(&[refspec.as_str()], Some(&mut fetch_opts), None)?;
 
-    // Get the commit ID
-    let commit_id = if let Some(tag_name) = tag {
-        let tag_ref = repo.find_reference(&format!("refs/tags/{}", tag_name))?;
-        tag_ref.peel_to_commit()?.id()
-    } else {
-        let branch_name = branch.unwrap_or(default_branch);
-        let reference = repo.find_reference(&format!("refs/heads/{}", branch_name))?;
-        reference.peel_to_commit()?.id()
+        // Get the commit ID
+        if let Some(tag_name) = tag {
+            let tag_ref = repo.find_reference(&format!("refs/tags/{tag_name}"))?;
+            tag_ref.peel_to_commit()?.id()
+        } else {
+            let branch_name = branch.ok_or("either a branch or a tag must be specified")?;
+            let reference = repo.find_reference(&format!("refs/heads/{branch_name}"))?;
+            reference.peel_to_commit()?.id()
+        }
     };
 
     Ok(commit_id.to_string())
 }
 
-fn clone_and_checkout_repo(
-    git_url: &str,
-    _branch: Option<&str>,
-    _tag: Option<&str>,
-    commit_sha: &str,
-) -> Result<tempfile::TempDir, Box<dyn Error>> {
-    let temp_dir = tempfile::Builder::new()
-        .prefix("docify-temp-")
-        .rand_bytes(5)
-        .tempdir()?;
-
-    let repo = Repository::init(temp_dir.path())?;
-
-    let mut callbacks = RemoteCallbacks::new();
-    callbacks.transfer_progress(|p| {
-        println!(
-            "📥 Fetching: {}/{} objects ({:.1}%)",
-            p.received_objects(),
-            p.total_objects(),
-            (p.received_objects() as f64 / p.total_objects() as f64) * 100.0
-        );
-        true
-    });
-
-    let mut fetch_opts = FetchOptions::new();
-    fetch_opts.remote_callbacks(callbacks);
-    fetch_opts.depth(1);
-
-    let mut remote = repo.remote_anonymous(git_url)?;
-
-    // Only fetch the specific commit we need
-    remote.fetch(
-        &[&format!("+{commit_sha}:refs/heads/temp")],
-        Some(&mut fetch_opts),
-        None,
-    )?;
-
-    // Checkout the specific commit
-    let commit_id = git2::Oid::from_str(commit_sha)?;
-    let commit = repo.find_commit(commit_id)?;
-    let tree = commit.tree()?;
-    repo.checkout_tree(tree.as_object(), None)?;
-    repo.set_head_detached(commit_id)?;
-
-    Ok(temp_dir)
-}
-
 // fn hash_path(path: &str) -> String {
 //     println!("ℹ️  Hashing path: {}", path);
 //     let mut hasher = Sha256::new();
@@ -350,7 +1079,7 @@ fn clone_and_checkout_repo(
 // }
 
 // Helper function to get default branch
-fn get_default_branch(git_url: &str) -> Result<String, Box<dyn Error>> {
+fn get_default_branch(git_url: &str, auth: &AuthConfig) -> Result<String, Box<dyn Error>> {
     let temp_dir = tempfile::Builder::new()
         .prefix("docify-temp-")
         .rand_bytes(5)
@@ -359,7 +1088,7 @@ fn get_default_branch(git_url: &str) -> Result<String, Box<dyn Error>> {
     let repo = Repository::init(temp_dir.path())?;
     let mut remote = repo.remote_anonymous(git_url)?;
 
-    remote.connect(git2::Direction::Fetch)?;
+    remote.connect_auth(git2::Direction::Fetch, Some(auth.callbacks()), None)?;
     let default_branch = remote
         .default_branch()?
         .as_str()
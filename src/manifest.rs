@@ -0,0 +1,134 @@
+//! The `snippets.toml` manifest and `snippets.lock` lockfile that back
+//! `git-hash sync`, analogous to how Cargo/Nix pin resolved revisions
+//! alongside a human-edited manifest.
+
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// One entry in `snippets.toml`: a single snippet to resolve and vendor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub git: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// An arbitrary revspec (e.g. `main~3`, `v1.2.3^{commit}`), resolved via
+    /// `git2::Repository::revparse_single` instead of branch/tag/commit.
+    #[serde(rename = "rev", skip_serializing_if = "Option::is_none")]
+    pub rev_spec: Option<String>,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_path: Option<String>,
+}
+
+impl ManifestEntry {
+    /// The human-readable revision selector this entry pins (a revspec, commit,
+    /// tag, or branch, in that priority order).
+    pub fn rev(&self) -> &str {
+        self.rev_spec
+            .as_deref()
+            .or(self.commit.as_deref())
+            .or(self.tag.as_deref())
+            .or(self.branch.as_deref())
+            .unwrap_or("HEAD")
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default, rename = "entry")]
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path)?;
+        Ok(toml::from_str(&text)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn append(&mut self, entry: ManifestEntry) {
+        self.entries.push(entry);
+    }
+}
+
+/// One entry in `snippets.lock`: the commit an entry resolved to last time,
+/// and the snippet filename(s) it was saved under (a `path` glob can match
+/// more than one file in the tree).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockEntry {
+    pub git: String,
+    pub rev: String,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+    pub commit: String,
+    pub snippets: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Lockfile {
+    #[serde(default, rename = "entry")]
+    pub entries: Vec<LockEntry>,
+}
+
+impl Lockfile {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path)?;
+        Ok(toml::from_str(&text)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Finds the lock entry matching a manifest entry's identity (remote,
+    /// revision selector, path, and item), if one was recorded by a prior run.
+    pub fn find(&self, entry: &ManifestEntry) -> Option<&LockEntry> {
+        self.entries.iter().find(|locked| {
+            locked.git == entry.git
+                && locked.rev == entry.rev()
+                && locked.path == entry.path
+                && locked.item == entry.item
+        })
+    }
+
+    /// Replaces any existing lock entry with the same identity as `entry` and
+    /// records a new one pinning it to `commit` and `snippets`.
+    pub fn upsert(&mut self, entry: &ManifestEntry, commit: String, snippets: Vec<String>) {
+        self.entries.retain(|locked| {
+            !(locked.git == entry.git
+                && locked.rev == entry.rev()
+                && locked.path == entry.path
+                && locked.item == entry.item)
+        });
+        self.entries.push(LockEntry {
+            git: entry.git.clone(),
+            rev: entry.rev().to_string(),
+            path: entry.path.clone(),
+            item: entry.item.clone(),
+            commit,
+            snippets,
+        });
+    }
+}